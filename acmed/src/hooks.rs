@@ -1,7 +1,9 @@
 use crate::certificate::Certificate;
 use crate::config::HookType;
 use acme_common::error::Error;
-use handlebars::Handlebars;
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
+};
 use log::debug;
 use serde::Serialize;
 use std::collections::hash_map::Iter;
@@ -10,7 +12,10 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
-use std::{env, fmt};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use std::{env, fmt, thread};
 
 pub trait HookEnvData {
     fn set_env(&mut self, env: &HashMap<String, String>);
@@ -85,8 +90,65 @@ pub struct Hook {
     pub stdout: Option<String>,
     pub stderr: Option<String>,
     pub allow_failure: bool,
+    pub timeout: Option<u64>,
+    pub action: HookAction,
+    // Hooks of the same `HookType` on a certificate run in ascending `order`;
+    // hooks sharing an `order` value are independent and may overlap.
+    pub order: i32,
+    pub retry: Option<Retry>,
 }
 
+// Optional retry policy for transient hook failures (typically DNS propagation
+// hooks that fail once and then succeed). Each attempt is re-rendered and gets
+// its own `timeout` budget.
+#[derive(Clone, Debug)]
+pub struct Retry {
+    pub max_retries: usize,
+    pub initial_delay_ms: u64,
+    pub backoff_multiplier: f64,
+    pub max_delay_ms: Option<u64>,
+}
+
+// Default worker-pool size for concurrent hook execution when a certificate
+// does not override it.
+pub const DEFAULT_MAX_PARALLEL_HOOKS: usize = 4;
+
+// The concrete action a hook performs. `Process` keeps the historical behavior
+// of shelling out (using the `cmd`/`args`/`stdin`/`stdout`/`stderr` fields of
+// the owning `Hook`); the remaining variants run natively so that trivial
+// actions no longer require wrapping in a shell script. Every template field is
+// rendered against the hook's `HookEnvData`/`Serialize` context before use.
+#[derive(Clone, Debug)]
+pub enum HookAction {
+    Process,
+    HttpRequest {
+        method: String,
+        url: String,
+        headers: Vec<(String, String)>,
+        body: Option<String>,
+    },
+    // `path` must name a regular file, not a blocking sink (FIFO, device, or
+    // other special file): the write has no internal cancellation, so if it
+    // blocks it only stops when the consumer drains it — the hook `timeout`
+    // abandons the worker thread but cannot unblock the write itself.
+    FileWrite {
+        path: String,
+        content: String,
+    },
+    Reload {
+        service: String,
+    },
+}
+
+impl Default for HookAction {
+    fn default() -> Self {
+        HookAction::Process
+    }
+}
+
+// Interval at which a timed hook's child process is polled for completion.
+const HOOK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 impl fmt::Display for Hook {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.name)
@@ -106,12 +168,447 @@ macro_rules! get_hook_output {
     }};
 }
 
+// Interpret a helper's string argument as a byte string. Templates thread raw
+// digests between helpers as latin1/byte-preserving strings (each `char` holds
+// a single byte), so the reverse mapping simply truncates each scalar value.
+fn helper_param_bytes(h: &Helper) -> Result<Vec<u8>, RenderError> {
+    let param = h
+        .param(0)
+        .and_then(|v| v.value().as_str())
+        .ok_or_else(|| RenderError::new("helper: missing string argument"))?;
+    Ok(param.chars().map(|c| c as u8).collect())
+}
+
+// Render a byte slice back into a byte-preserving latin1 string.
+fn bytes_to_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| *b as char).collect()
+}
+
+const HEX_ALPHABET: &[u8; 16] = b"0123456789abcdef";
+const B64_STD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const B64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn hex_encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        s.push(HEX_ALPHABET[(b >> 4) as usize] as char);
+        s.push(HEX_ALPHABET[(b & 0x0f) as usize] as char);
+    }
+    s
+}
+
+// RFC 4648 base64 with the given alphabet. Padding is emitted only for the
+// standard alphabet; the URL-safe variant (§5) is used without padding as
+// required by the ACME/JWS conventions used elsewhere in the crate.
+fn b64_encode(bytes: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut s = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0] as usize;
+        let b1 = chunk.get(1).map(|b| *b as usize).unwrap_or(0);
+        let b2 = chunk.get(2).map(|b| *b as usize).unwrap_or(0);
+        s.push(alphabet[b0 >> 2] as char);
+        s.push(alphabet[((b0 & 0x03) << 4) | (b1 >> 4)] as char);
+        match chunk.len() {
+            1 => {
+                if pad {
+                    s.push('=');
+                    s.push('=');
+                }
+            }
+            2 => {
+                s.push(alphabet[((b1 & 0x0f) << 2) | (b2 >> 6)] as char);
+                if pad {
+                    s.push('=');
+                }
+            }
+            _ => {
+                s.push(alphabet[((b1 & 0x0f) << 2) | (b2 >> 6)] as char);
+                s.push(alphabet[b2 & 0x3f] as char);
+            }
+        }
+    }
+    s
+}
+
+macro_rules! encoding_helper {
+    ($name: ident, $body: expr) => {
+        fn $name(
+            h: &Helper,
+            _: &Handlebars,
+            _: &Context,
+            _: &mut RenderContext,
+            out: &mut dyn Output,
+        ) -> HelperResult {
+            let bytes = helper_param_bytes(h)?;
+            let f: fn(Vec<u8>) -> String = $body;
+            out.write(&f(bytes))?;
+            Ok(())
+        }
+    };
+}
+
+encoding_helper!(helper_sha256, |b| bytes_to_latin1(&openssl::sha::sha256(&b)));
+encoding_helper!(helper_hex, |b| hex_encode(&b));
+encoding_helper!(helper_b64, |b| b64_encode(&b, B64_STD, true));
+encoding_helper!(helper_b64url, |b| b64_encode(&b, B64_URL, false));
+encoding_helper!(helper_lower, |b| bytes_to_latin1(&b).to_lowercase());
+encoding_helper!(helper_upper, |b| bytes_to_latin1(&b).to_uppercase());
+
+// Register the encoding helpers usable from any hook template string.
+fn register_hook_helpers(reg: &mut Handlebars) {
+    reg.register_helper("sha256", Box::new(helper_sha256));
+    reg.register_helper("hex", Box::new(helper_hex));
+    reg.register_helper("b64", Box::new(helper_b64));
+    reg.register_helper("b64url", Box::new(helper_b64url));
+    reg.register_helper("lower", Box::new(helper_lower));
+    reg.register_helper("upper", Box::new(helper_upper));
+}
+
+// Internal error carrying whether the failure is worth retrying. Execution and
+// exit-code failures (timeouts, non-zero exits, non-2xx responses, transport
+// errors) are retryable; configuration errors (template render, spawn ENOENT,
+// invalid method/URL, empty command) are not and must fail fast.
+struct HookError {
+    msg: String,
+    retryable: bool,
+}
+
+impl HookError {
+    fn exec<S: Into<String>>(msg: S) -> Self {
+        HookError {
+            msg: msg.into(),
+            retryable: true,
+        }
+    }
+
+    fn config<S: Into<String>>(msg: S) -> Self {
+        HookError {
+            msg: msg.into(),
+            retryable: false,
+        }
+    }
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl From<HookError> for Error {
+    fn from(e: HookError) -> Self {
+        e.msg.into()
+    }
+}
+
+// Errors reachable via `?` are configuration/setup failures by default and are
+// not retried; genuine execution failures are built explicitly with `exec`.
+impl From<handlebars::RenderError> for HookError {
+    fn from(e: handlebars::RenderError) -> Self {
+        HookError::config(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for HookError {
+    fn from(e: std::io::Error) -> Self {
+        HookError::config(e.to_string())
+    }
+}
+
+impl From<attohttpc::Error> for HookError {
+    fn from(e: attohttpc::Error) -> Self {
+        HookError::config(e.to_string())
+    }
+}
+
+impl From<&str> for HookError {
+    fn from(e: &str) -> Self {
+        HookError::config(e.to_string())
+    }
+}
+
+impl From<String> for HookError {
+    fn from(e: String) -> Self {
+        HookError::config(e)
+    }
+}
+
+fn timeout_error(hook: &Hook) -> Result<(), HookError> {
+    if hook.allow_failure {
+        debug!(
+            "Hook {}: timed out after {}s (ignored)",
+            hook.name,
+            hook.timeout.unwrap_or_default()
+        );
+        return Ok(());
+    }
+    Err(HookError::exec(format!(
+        "Hook {}: timed out after {}s",
+        hook.name,
+        hook.timeout.unwrap_or_default()
+    )))
+}
+
 fn call_single<T>(data: &T, hook: &Hook) -> Result<(), Error>
 where
     T: Clone + HookEnvData + Serialize,
 {
     debug!("Calling hook: {}", hook.name);
-    let reg = Handlebars::new();
+    let mut reg = Handlebars::new();
+    register_hook_helpers(&mut reg);
+    // A single attempt: re-rendered on every call so each retry observes the
+    // current environment, and each attempt gets its own `timeout` budget.
+    let attempt = || match &hook.action {
+        HookAction::Process => call_process(data, hook, &reg),
+        HookAction::HttpRequest { .. } => call_native(hook, call_http(data, hook, &reg)),
+        HookAction::FileWrite { .. } => call_native(hook, call_file_write(data, hook, &reg)),
+        HookAction::Reload { .. } => call_native(hook, call_reload(data, hook, &reg)),
+    };
+    match &hook.retry {
+        // `allow_failure` hooks only surface non-retryable errors from `attempt`,
+        // so the retry loop only ever re-runs genuinely failing, non-tolerated hooks.
+        // Only execution/exit failures are retried; a misconfigured hook
+        // (render/spawn/config error) fails fast without burning the backoff.
+        // `max_retries` bounds the total number of runs (the initial attempt
+        // included), so `max_retries = 3` means at most three executions.
+        Some(retry) if retry.max_retries > 0 => {
+            let mut tries = 0;
+            loop {
+                match attempt() {
+                    Ok(()) => return Ok(()),
+                    Err(e) => {
+                        if !e.retryable || tries + 1 >= retry.max_retries {
+                            return Err(e.into());
+                        }
+                        let delay = retry_delay(retry, tries);
+                        debug!(
+                            "Hook {}: attempt {} failed: {}; retrying in {}ms",
+                            hook.name,
+                            tries + 1,
+                            e,
+                            delay
+                        );
+                        thread::sleep(Duration::from_millis(delay));
+                        tries += 1;
+                    }
+                }
+            }
+        }
+        _ => attempt().map_err(Into::into),
+    }
+}
+
+// Backoff delay (ms) before retry attempt `n` (0-indexed), capped at the
+// optional `max_delay_ms`.
+fn retry_delay(retry: &Retry, n: usize) -> u64 {
+    let delay = retry.initial_delay_ms as f64 * retry.backoff_multiplier.powi(n as i32);
+    let delay = delay.round() as u64;
+    match retry.max_delay_ms {
+        Some(max) => delay.min(max),
+        None => delay,
+    }
+}
+
+// Apply the shared `timeout`/`allow_failure` semantics to a natively executed
+// action. The work runs on a worker thread so a runaway action (e.g. an HTTP
+// request to an unresponsive server) is bounded by the same deadline as a
+// process hook; on timeout the thread is detached and reported as a failure.
+//
+// Like `call_process`, `allow_failure` only tolerates execution/exit failures
+// (`HookError::retryable`): a misconfigured hook (un-renderable template,
+// invalid method/URL) fails regardless so operators are not left with a hook
+// that silently never runs.
+fn call_native<F>(hook: &Hook, work: F) -> Result<(), HookError>
+where
+    F: FnOnce() -> Result<(), HookError> + Send + 'static,
+{
+    let outcome = match hook.timeout {
+        Some(secs) => {
+            let handle = thread::spawn(work);
+            let deadline = Instant::now() + Duration::from_secs(secs);
+            loop {
+                if handle.is_finished() {
+                    break handle
+                        .join()
+                        .map_err(|_| HookError::exec(format!("Hook {}: panicked", hook.name)))?;
+                }
+                if Instant::now() >= deadline {
+                    return timeout_error(hook);
+                }
+                thread::sleep(HOOK_POLL_INTERVAL);
+            }
+        }
+        None => work(),
+    };
+    match outcome {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            if hook.allow_failure && e.retryable {
+                debug!("Hook {}: {} (ignored)", hook.name, e);
+                Ok(())
+            } else {
+                Err(e)
+            }
+        }
+    }
+}
+
+// Render the HTTP action fields and issue the request using the crate's HTTP
+// client. A non-2xx response is treated as a hook failure so that challenge
+// propagation to a DNS provider's API surfaces errors like any other hook.
+fn call_http<T>(data: &T, hook: &Hook, reg: &Handlebars) -> impl FnOnce() -> Result<(), HookError> + Send + 'static
+where
+    T: Serialize,
+{
+    let render = |tpl: &str| reg.render_template(tpl, data);
+    let prepared = (|| -> Result<(String, String, Vec<(String, String)>, Option<String>), HookError> {
+        if let HookAction::HttpRequest {
+            method,
+            url,
+            headers,
+            body,
+        } = &hook.action
+        {
+            let method = render(method)?;
+            let url = render(url)?;
+            let mut rendered_headers = Vec::with_capacity(headers.len());
+            for (name, value) in headers {
+                rendered_headers.push((render(name)?, render(value)?));
+            }
+            let body = match body {
+                Some(b) => Some(render(b)?),
+                None => None,
+            };
+            Ok((method, url, rendered_headers, body))
+        } else {
+            unreachable!("call_http called with a non-HttpRequest action")
+        }
+    })();
+    let name = hook.name.clone();
+    let timeout = hook.timeout;
+    move || {
+        let (method, url, headers, body) = prepared?;
+        debug!("Hook {}: {} {}", name, method, url);
+        let method = attohttpc::Method::from_bytes(method.as_bytes())
+            .map_err(|e| HookError::config(format!("Hook {}: invalid HTTP method: {}", name, e)))?;
+        let mut req = attohttpc::RequestBuilder::try_new(method, &url)?;
+        for (header_name, header_value) in &headers {
+            req = req.header(header_name, header_value);
+        }
+        // Bound the connect and each read by the hook timeout so an unresponsive
+        // server cannot keep the connection (and the worker thread) alive forever.
+        if let Some(secs) = timeout {
+            let d = Duration::from_secs(secs);
+            req = req.connect_timeout(d).read_timeout(d);
+        }
+        // A transport failure (connection refused, read/connect timeout) is a
+        // transient execution error and is retryable, unlike the config errors
+        // above.
+        let resp = match body {
+            Some(b) => req.text(b).send(),
+            None => req.send(),
+        }
+        .map_err(|e| HookError::exec(format!("Hook {}: request failed: {}", name, e)))?;
+        if !resp.is_success() {
+            return Err(HookError::exec(format!(
+                "Hook {}: HTTP status {}",
+                name,
+                resp.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+// Render and write the target file, replacing the common "echo > file" pattern
+// used for the HTTP-01 challenge. The write is not cancellable: `path` must be
+// a regular file (see `HookAction::FileWrite`), otherwise a blocking sink can
+// outlive the hook `timeout` by stranding the worker thread on the write.
+fn call_file_write<T>(
+    data: &T,
+    hook: &Hook,
+    reg: &Handlebars,
+) -> impl FnOnce() -> Result<(), HookError> + Send + 'static
+where
+    T: Serialize,
+{
+    let prepared = (|| -> Result<(String, String), HookError> {
+        if let HookAction::FileWrite { path, content } = &hook.action {
+            Ok((reg.render_template(path, data)?, reg.render_template(content, data)?))
+        } else {
+            unreachable!("call_file_write called with a non-FileWrite action")
+        }
+    })();
+    let name = hook.name.clone();
+    move || {
+        let (path, content) = prepared?;
+        debug!("Hook {}: writing {}", name, path);
+        let mut file = File::create(&path)?;
+        file.write_all(content.as_bytes())?;
+        Ok(())
+    }
+}
+
+// Render the configured reload command and run it, reaping the child. Unlike a
+// `Process` hook this is a single rendered command line for the common case of
+// signalling a daemon to reload its configuration.
+fn call_reload<T>(data: &T, hook: &Hook, reg: &Handlebars) -> impl FnOnce() -> Result<(), HookError> + Send + 'static
+where
+    T: Serialize,
+{
+    let prepared = (|| -> Result<String, HookError> {
+        if let HookAction::Reload { service } = &hook.action {
+            Ok(reg.render_template(service, data)?)
+        } else {
+            unreachable!("call_reload called with a non-Reload action")
+        }
+    })();
+    let name = hook.name.clone();
+    let timeout = hook.timeout;
+    move || {
+        let service = prepared?;
+        debug!("Hook {}: reloading {}", name, service);
+        let mut parts = service.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| HookError::config(format!("Hook {}: empty reload command", name)))?;
+        let mut child = Command::new(program).args(parts).spawn()?;
+        // Spawn rather than `status()` so a hung reload command is killed and
+        // reaped when its own deadline expires instead of outliving it.
+        let deadline = timeout.map(|s| Instant::now() + Duration::from_secs(s));
+        let status = match deadline {
+            Some(deadline) => loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                if Instant::now() >= deadline {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(HookError::exec(format!(
+                        "Hook {}: timed out after {}s",
+                        name,
+                        timeout.unwrap_or_default()
+                    )));
+                }
+                thread::sleep(HOOK_POLL_INTERVAL);
+            },
+            None => child.wait()?,
+        };
+        if !status.success() {
+            let msg = match status.code() {
+                Some(code) => format!("Hook {}: reload failed: code {}", name, code),
+                None => format!("Hook {}: reload failed", name),
+            };
+            return Err(HookError::exec(msg));
+        }
+        Ok(())
+    }
+}
+
+fn call_process<T>(data: &T, hook: &Hook, reg: &Handlebars) -> Result<(), HookError>
+where
+    T: Clone + HookEnvData + Serialize,
+{
     let mut v = vec![];
     let args = match &hook.args {
         Some(lst) => {
@@ -135,20 +632,50 @@ where
             None => Stdio::null(),
         })
         .spawn()?;
+    let deadline = hook.timeout.map(|s| Instant::now() + Duration::from_secs(s));
     if hook.stdin.is_some() {
         let data_in = reg.render_template(&hook.stdin.to_owned().unwrap(), &data)?;
         debug!("Hook {}: stdin: {}", hook.name, data_in);
-        let stdin = cmd.stdin.as_mut().ok_or("stdin not found")?;
-        stdin.write_all(data_in.as_bytes())?;
+        let mut stdin = cmd.stdin.take().ok_or("stdin not found")?;
+        // Writing must be bounded by the same deadline so a hook that never
+        // drains its stdin cannot block the writer forever: perform the write
+        // on a dedicated thread and abandon it (killing the child) on timeout.
+        let writer = thread::spawn(move || stdin.write_all(data_in.as_bytes()));
+        loop {
+            if writer.is_finished() {
+                writer
+                    .join()
+                    .map_err(|_| "stdin writer panicked".to_string())??;
+                break;
+            }
+            if deadline.map(|d| Instant::now() >= d).unwrap_or(false) {
+                let _ = cmd.kill();
+                let _ = cmd.wait();
+                return timeout_error(hook);
+            }
+            thread::sleep(HOOK_POLL_INTERVAL);
+        }
     }
-    // TODO: add a timeout
-    let status = cmd.wait()?;
+    let status = match deadline {
+        Some(deadline) => loop {
+            if let Some(status) = cmd.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = cmd.kill();
+                let _ = cmd.wait();
+                return timeout_error(hook);
+            }
+            thread::sleep(HOOK_POLL_INTERVAL);
+        },
+        None => cmd.wait()?,
+    };
     if !status.success() && !hook.allow_failure {
         let msg = match status.code() {
             Some(code) => format!("Hook {}: unrecoverable failure: code {}", hook.name, code),
             None => format!("Hook {}: unrecoverable failure", hook.name),
         };
-        return Err(msg.into());
+        return Err(HookError::exec(msg));
     }
     match status.code() {
         Some(code) => debug!("Hook {}: exited: code {}", hook.name, code),
@@ -157,16 +684,184 @@ where
     Ok(())
 }
 
+// Split the matching hooks into ordered execution groups: ascending by `order`,
+// with hooks that share an `order` value collected into the same (concurrent)
+// group. The sort is stable, so configuration order is preserved within a group.
+fn order_groups<'a>(hooks: &[&'a Hook]) -> Vec<Vec<&'a Hook>> {
+    let mut hooks = hooks.to_vec();
+    hooks.sort_by_key(|h| h.order);
+    let mut groups: Vec<Vec<&Hook>> = vec![];
+    for hook in hooks {
+        match groups.last_mut() {
+            Some(group) if group[0].order == hook.order => group.push(hook),
+            _ => groups.push(vec![hook]),
+        }
+    }
+    groups
+}
+
 pub fn call<T>(cert: &Certificate, data: &T, hook_type: HookType) -> Result<(), Error>
 where
-    T: Clone + HookEnvData + Serialize,
+    T: Clone + HookEnvData + Serialize + Sync,
 {
-    for hook in cert
+    let hooks: Vec<&Hook> = cert
         .hooks
         .iter()
         .filter(|h| h.hook_type.contains(&hook_type))
-    {
-        call_single(data, &hook)?;
+        .collect();
+    // An unset (0) `max_parallel_hooks` falls back to the crate default.
+    let max_parallel = match cert.max_parallel_hooks {
+        0 => DEFAULT_MAX_PARALLEL_HOOKS,
+        n => n,
+    };
+
+    let mut failures: Vec<String> = vec![];
+    // Each order group is a barrier: every hook in the group must finish before
+    // the next group starts. Within a group the hooks are executed on a bounded
+    // pool of `max_parallel` workers that pull from a shared queue, so a freed
+    // worker immediately starts the next hook instead of idling until a slow
+    // (e.g. retrying/backing-off) peer in a fixed chunk completes.
+    for group in order_groups(&hooks) {
+        let group = group.as_slice();
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<(usize, String)>> = Mutex::new(vec![]);
+        thread::scope(|s| {
+            for _ in 0..max_parallel.min(group.len()) {
+                s.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= group.len() {
+                        break;
+                    }
+                    // Execution errors already carry the hook name; prefix it
+                    // onto the others (render, spawn, I/O) so every entry names its hook.
+                    if let Err(e) = call_single(data, group[i]) {
+                        let msg = e.to_string();
+                        let prefix = format!("Hook {}:", group[i].name);
+                        let msg = if msg.starts_with(&prefix) {
+                            msg
+                        } else {
+                            format!("{} {}", prefix, msg)
+                        };
+                        results.lock().unwrap().push((i, msg));
+                    }
+                });
+            }
+        });
+        let mut group_failures = results.into_inner().unwrap();
+        group_failures.sort_by_key(|(i, _)| *i);
+        failures.extend(group_failures.into_iter().map(|(_, msg)| msg));
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("hook failures: {}", failures.join("; ")).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_rfc4648_vectors() {
+        assert_eq!(hex_encode(b""), "");
+        assert_eq!(hex_encode(b"f"), "66");
+        assert_eq!(hex_encode(b"foobar"), "666f6f626172");
+        assert_eq!(hex_encode(&[0x00, 0xff, 0x10]), "00ff10");
+    }
+
+    #[test]
+    fn base64_rfc4648_vectors() {
+        // RFC 4648 §10 test vectors (standard alphabet, with padding).
+        assert_eq!(b64_encode(b"", B64_STD, true), "");
+        assert_eq!(b64_encode(b"f", B64_STD, true), "Zg==");
+        assert_eq!(b64_encode(b"fo", B64_STD, true), "Zm8=");
+        assert_eq!(b64_encode(b"foo", B64_STD, true), "Zm9v");
+        assert_eq!(b64_encode(b"foob", B64_STD, true), "Zm9vYg==");
+        assert_eq!(b64_encode(b"fooba", B64_STD, true), "Zm9vYmE=");
+        assert_eq!(b64_encode(b"foobar", B64_STD, true), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn base64url_no_padding_uses_url_alphabet() {
+        // Bytes that exercise the `+/` vs `-_` difference: 0xfb 0xef 0xff -> "--__".
+        assert_eq!(b64_encode(&[0xfb, 0xef, 0xff], B64_STD, true), "++//");
+        assert_eq!(b64_encode(&[0xfb, 0xef, 0xff], B64_URL, false), "--__");
+        // Padding is omitted for the URL-safe variant.
+        assert_eq!(b64_encode(b"f", B64_URL, false), "Zg");
+        assert_eq!(b64_encode(b"fo", B64_URL, false), "Zm8");
+    }
+
+    fn retry(initial_delay_ms: u64, multiplier: f64, max_delay_ms: Option<u64>) -> Retry {
+        Retry {
+            max_retries: 10,
+            initial_delay_ms,
+            backoff_multiplier: multiplier,
+            max_delay_ms,
+        }
+    }
+
+    #[test]
+    fn retry_delay_exponential_backoff() {
+        let r = retry(100, 2.0, None);
+        assert_eq!(retry_delay(&r, 0), 100);
+        assert_eq!(retry_delay(&r, 1), 200);
+        assert_eq!(retry_delay(&r, 2), 400);
+        assert_eq!(retry_delay(&r, 3), 800);
+    }
+
+    #[test]
+    fn retry_delay_respects_max_delay() {
+        let r = retry(100, 2.0, Some(500));
+        assert_eq!(retry_delay(&r, 0), 100);
+        assert_eq!(retry_delay(&r, 2), 400);
+        assert_eq!(retry_delay(&r, 3), 500);
+        assert_eq!(retry_delay(&r, 10), 500);
+    }
+
+    #[test]
+    fn retry_delay_saturates_but_is_capped() {
+        // A large attempt count overflows f64 -> `INFINITY as u64` saturates to
+        // u64::MAX; an operator-set cap keeps that from sleeping ~forever.
+        let uncapped = retry(1000, 2.0, None);
+        assert_eq!(retry_delay(&uncapped, 4096), u64::MAX);
+        let capped = retry(1000, 2.0, Some(60_000));
+        assert_eq!(retry_delay(&capped, 4096), 60_000);
+    }
+
+    fn process_hook(name: &str, order: i32) -> Hook {
+        Hook {
+            name: name.to_string(),
+            hook_type: vec![],
+            cmd: "true".to_string(),
+            args: None,
+            stdin: None,
+            stdout: None,
+            stderr: None,
+            allow_failure: false,
+            timeout: None,
+            action: HookAction::Process,
+            order,
+            retry: None,
+        }
+    }
+
+    #[test]
+    fn order_groups_sorts_and_groups_by_order() {
+        let hooks = vec![
+            process_hook("c", 2),
+            process_hook("a", 1),
+            process_hook("b", 1),
+            process_hook("d", 3),
+        ];
+        let refs: Vec<&Hook> = hooks.iter().collect();
+        let groups = order_groups(&refs);
+        let names: Vec<Vec<&str>> = groups
+            .iter()
+            .map(|g| g.iter().map(|h| h.name.as_str()).collect())
+            .collect();
+        // Ascending order, stable within a group (a before b at order 1).
+        assert_eq!(names, vec![vec!["a", "b"], vec!["c"], vec!["d"]]);
     }
-    Ok(())
 }